@@ -1,5 +1,5 @@
-#![feature(once_cell)]
-
+use flate2::read::ZlibDecoder;
+use lru::LruCache;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use select::document::Document;
@@ -7,9 +7,194 @@ use select::node::{Data, Node};
 use select::predicate::{Attr, Class, Name, Predicate};
 
 use std::collections::HashMap;
-use std::lazy::SyncOnceCell;
+use std::io::Read as _;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Pages are only ever re-parsed on a cache miss, so a generous default
+/// keeps a long-running bot from re-requesting/re-parsing pages it has
+/// already crawled while still bounding memory.
+const DEFAULT_DOCUMENT_STORE_CAPACITY: usize = 256;
+
+static DOCUMENT_STORE: OnceLock<RwLock<LruCache<String, Arc<Document>>>> = OnceLock::new();
+
+fn document_store() -> &'static RwLock<LruCache<String, Arc<Document>>> {
+    DOCUMENT_STORE.get_or_init(|| {
+        RwLock::new(LruCache::new(
+            NonZeroUsize::new(DEFAULT_DOCUMENT_STORE_CAPACITY).unwrap(),
+        ))
+    })
+}
+
+/// Resizes the document cache, evicting least-recently-used pages
+/// immediately if shrinking. Useful for long-running bots that want to
+/// trade memory for a higher cache hit rate (or vice versa).
+#[pyfunction]
+fn set_document_store_capacity(capacity: usize) {
+    let capacity = NonZeroUsize::new(capacity).unwrap_or_else(|| NonZeroUsize::new(1).unwrap());
+    document_store().write().unwrap().resize(capacity);
+}
+
+/// Returns the cached `Document` for `url`, parsing and caching `html`
+/// first if it isn't already there. The lookup (and, on a miss, the
+/// insert) each take the lock only long enough to touch the LRU cache;
+/// parsing itself happens outside any critical section.
+fn get_or_insert_document(url: &str, html: &str) -> Arc<Document> {
+    if let Some(document) = document_store().write().unwrap().get(url) {
+        return Arc::clone(document);
+    }
+
+    let document = Arc::new(Document::from(html));
+
+    document_store()
+        .write()
+        .unwrap()
+        .put(url.to_string(), Arc::clone(&document));
+
+    document
+}
+
+/// One crawled `dt[id]` entry: an anchor the bot can jump straight to via
+/// `scrape_document`, a plain-text signature name, and a short blurb pulled
+/// from the first sentence of its `dd` description.
+#[pyclass]
+#[derive(Clone)]
+struct IndexEntry {
+    #[pyo3(get)]
+    anchor: String,
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    description: String,
+}
+
+static INDEX_STORE: OnceLock<RwLock<HashMap<String, Vec<IndexEntry>>>> = OnceLock::new();
+
+fn index_store() -> &'static RwLock<HashMap<String, Vec<IndexEntry>>> {
+    INDEX_STORE.get_or_init(|| RwLock::new(HashMap::new()))
+}
 
-static mut DOCUMENT_STORE: SyncOnceCell<HashMap<String, Document>> = SyncOnceCell::new();
+fn first_sentence(text: &str) -> String {
+    let trimmed = text.trim();
+
+    match trimmed.find(". ") {
+        Some(idx) => trimmed[..=idx].trim_end().to_string(),
+        None => trimmed.split('\n').next().unwrap_or("").trim().to_string(),
+    }
+}
+
+/// Crawls every `dt` element carrying an `id` attribute in `html` and
+/// records it as an [`IndexEntry`], keyed alongside the cached `Document`
+/// under `url` in `INDEX_STORE`. Returns the number of entries indexed.
+///
+/// This lets the bot offer autocomplete-style search (see [`search_index`])
+/// without re-parsing the page or round-tripping per keystroke.
+#[pyfunction]
+fn build_index(url: &str, html: &str) -> PyResult<usize> {
+    let document = get_or_insert_document(url, html);
+    let mut entries: Vec<IndexEntry> = Vec::new();
+
+    for dt in document.find(Name("dt").and(Attr("id", ()))) {
+        let anchor = dt.attr("id").unwrap().to_string();
+
+        let name = dt
+            .find(Class("descname").or(Class("sig-name")))
+            .next()
+            .map(|span| span.text())
+            .unwrap_or_else(|| dt.text());
+
+        let description = match dt.parent().and_then(|parent| parent.find(Name("dd")).next()) {
+            Some(dd) => first_sentence(&dd.text()),
+            None => String::new(),
+        };
+
+        entries.push(IndexEntry {
+            anchor,
+            name: name.trim().to_string(),
+            description,
+        });
+    }
+
+    let count = entries.len();
+
+    index_store().write().unwrap().insert(url.to_string(), entries);
+
+    Ok(count)
+}
+
+/// Iterative two-row Levenshtein distance, bailing out early once every
+/// entry in the current row exceeds `max` (the caller only cares whether
+/// the distance is within `max`, not its exact value beyond that).
+fn levenshtein(a: &str, b: &str, max: usize) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        let mut row_min = curr[0];
+
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            row_min = row_min.min(curr[j]);
+        }
+
+        if row_min > max {
+            return max + 1;
+        }
+
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+fn last_path_component(name: &str) -> String {
+    name.rsplit('.').next().unwrap_or(name).to_lowercase()
+}
+
+/// Ranks the `url` page's crawled index against `query` and returns the top
+/// `limit` matches: exact prefix match on the lowercased last path
+/// component first, then substring match, then by Levenshtein distance.
+#[pyfunction]
+fn search_index(url: &str, query: &str, limit: usize) -> Vec<IndexEntry> {
+    let entries = match index_store().read().unwrap().get(url) {
+        Some(entries) => entries.clone(),
+        None => return Vec::new(),
+    };
+
+    let query = query.to_lowercase();
+    let max_distance = limit + 1;
+
+    let mut scored: Vec<(u8, usize, IndexEntry)> = entries
+        .into_iter()
+        .map(|entry| {
+            let component = last_path_component(&entry.name);
+
+            let (tier, distance) = if component.starts_with(&query) {
+                (0, 0)
+            } else if component.contains(&query) {
+                (1, 0)
+            } else {
+                let cutoff = max_distance.min(component.len());
+                (2, levenshtein(&component, &query, cutoff))
+            };
+
+            (tier, distance, entry)
+        })
+        .collect();
+
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, entry)| entry)
+        .collect()
+}
 
 /// This function exists as requests to HTML are done in Python.
 ///
@@ -17,13 +202,206 @@ static mut DOCUMENT_STORE: SyncOnceCell<HashMap<String, Document>> = SyncOnceCel
 /// don't make a request.
 #[pyfunction]
 fn has_document(url: &str) -> bool {
-    unsafe {
-        if let Some(store) = DOCUMENT_STORE.get() {
-            store.contains_key(url)
+    document_store().read().unwrap().contains(url)
+}
+
+/// An object name resolved from a Sphinx `objects.inv` inventory to an
+/// absolute URL, split into the page `uri` and its fragment `anchor` so
+/// callers can join them (or just use `uri` when `anchor` is empty).
+#[pyclass]
+#[derive(Clone)]
+struct ResolvedRef {
+    #[pyo3(get)]
+    uri: String,
+    #[pyo3(get)]
+    anchor: String,
+    #[pyo3(get)]
+    display_name: String,
+}
+
+static INVENTORY_STORE: OnceLock<RwLock<HashMap<String, HashMap<String, ResolvedRef>>>> =
+    OnceLock::new();
+
+fn inventory_store() -> &'static RwLock<HashMap<String, HashMap<String, ResolvedRef>>> {
+    INVENTORY_STORE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Splits off the four plaintext header lines of a v2 `objects.inv` file
+/// (`# Sphinx inventory version 2`, `# Project: ...`, `# Version: ...`,
+/// `# The remainder ... zlib`), returning `(header, compressed_body)`.
+fn split_inventory_header(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut newlines = 0;
+
+    for (idx, byte) in bytes.iter().enumerate() {
+        if *byte == b'\n' {
+            newlines += 1;
+
+            if newlines == 4 {
+                return Some((&bytes[..=idx], &bytes[idx + 1..]));
+            }
+        }
+    }
+
+    None
+}
+
+/// Splits one decompressed inventory line of the form
+/// `name domain:role priority uri dispname` into its five fields. `dispname`
+/// is whatever remains on the line, since it's the only field allowed to
+/// contain whitespace.
+fn split_inventory_line(line: &str) -> Option<(&str, &str, &str, &str, &str)> {
+    let mut rest = line;
+    let mut tokens: Vec<&str> = Vec::with_capacity(4);
+
+    for _ in 0..4 {
+        let trimmed = rest.trim_start();
+        let space_idx = trimmed.find(char::is_whitespace)?;
+        tokens.push(&trimmed[..space_idx]);
+        rest = &trimmed[space_idx..];
+    }
+
+    Some((tokens[0], tokens[1], tokens[2], tokens[3], rest.trim_start()))
+}
+
+/// Downloads... well, decompresses and parses an already-fetched `objects.inv`
+/// body (fetching is left to Python, same as `scrape_document`'s HTML) and
+/// populates `INVENTORY_STORE` under `base_url`. Returns the number of
+/// object names indexed.
+#[pyfunction]
+fn load_inventory(base_url: &str, bytes: &[u8]) -> PyResult<usize> {
+    let (_header, compressed) = split_inventory_header(bytes)
+        .ok_or_else(|| PyErr::new::<PyValueError, _>("Malformed objects.inv: missing header"))?;
+
+    let mut decompressed = String::new();
+    ZlibDecoder::new(compressed)
+        .read_to_string(&mut decompressed)
+        .map_err(|err| PyErr::new::<PyValueError, _>(format!("Could not inflate inventory: {}", err)))?;
+
+    let base = base_url.trim_end_matches('/');
+    let mut entries: HashMap<String, ResolvedRef> = HashMap::new();
+
+    for line in decompressed.lines() {
+        let (name, _domain_role, _priority, uri, dispname) = match split_inventory_line(line) {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        let expanded_uri = if uri.ends_with('$') {
+            format!("{}{}", &uri[..uri.len() - 1], name)
         } else {
-            false
+            uri.to_string()
+        };
+
+        let (page, anchor) = match expanded_uri.find('#') {
+            Some(idx) => (
+                expanded_uri[..idx].to_string(),
+                expanded_uri[idx + 1..].to_string(),
+            ),
+            None => (expanded_uri, String::new()),
+        };
+
+        let display_name = if dispname == "-" {
+            name.to_string()
+        } else {
+            dispname.to_string()
+        };
+
+        entries.insert(
+            name.to_string(),
+            ResolvedRef {
+                uri: format!("{}/{}", base, page),
+                anchor,
+                display_name,
+            },
+        );
+    }
+
+    let count = entries.len();
+
+    inventory_store()
+        .write()
+        .unwrap()
+        .insert(base_url.to_string(), entries);
+
+    Ok(count)
+}
+
+/// Looks `name` up in the inventory loaded for `base_url` and returns its
+/// full URL (page plus `#anchor` when there is one). `url` may be either the
+/// exact project root an inventory was `load_inventory`'d under, or any page
+/// URL beneath it (e.g. the `url` a crawled page was scraped from) — the
+/// longest loaded inventory key that's a prefix of `url` is used, since a
+/// page's own URL is never the key the inventory itself was stored under.
+#[pyfunction]
+fn resolve_reference(url: &str, name: &str) -> Option<String> {
+    let store = inventory_store().read().unwrap();
+
+    let base = store
+        .keys()
+        .filter(|base| url.starts_with(base.as_str()))
+        .max_by_key(|base| base.len())?;
+
+    let resolved = store.get(base)?.get(name)?;
+
+    if resolved.anchor.is_empty() {
+        Some(resolved.uri.clone())
+    } else {
+        Some(format!("{}#{}", resolved.uri, resolved.anchor))
+    }
+}
+
+/// Whether `text` looks like a bare qualified Python name (e.g.
+/// `some.module.Thing`) rather than ordinary link text — the shape a
+/// `:py:class:`-style cross-reference's visible text takes.
+fn looks_like_domain_reference(text: &str) -> bool {
+    !text.is_empty()
+        && text.contains('.')
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '.' || c == '_')
+}
+
+/// Joins a (possibly relative) `href` found on `base` the way a browser
+/// would, instead of blindly concatenating the two. A bare `#anchor` stays
+/// on the current page; a root-relative `/path` is resolved against `base`'s
+/// origin; anything else is resolved relative to `base`'s directory, folding
+/// `../` segments along the way (never past the origin).
+fn join_url(base: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+
+    if href.starts_with('#') {
+        return format!("{}{}", base, href);
+    }
+
+    let base_dir = match base.rfind('/') {
+        Some(idx) => &base[..idx],
+        None => base,
+    };
+
+    let mut segments: Vec<&str> = base_dir.split('/').collect();
+    // Leading segments forming the origin (`https:`, ``, `host`), which
+    // neither a root-relative href nor `../` may strip.
+    let origin_len = if base.contains("://") { 3 } else { 1 };
+
+    if href.starts_with('/') {
+        segments.truncate(origin_len);
+    }
+
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if segments.len() > origin_len {
+                    segments.pop();
+                }
+            }
+            _ => segments.push(part),
         }
     }
+
+    segments.join("/")
 }
 
 enum WrappedNode<'n> {
@@ -31,6 +409,14 @@ enum WrappedNode<'n> {
     Text(String),
 }
 
+/// Direct (non-descendant) element children of `node` named `tag`, e.g. the
+/// `tr`s that belong to one `tbody` rather than every `tr` in the subtree.
+fn direct_children<'n>(node: Node<'n>, tag: &str) -> Vec<Node<'n>> {
+    node.children()
+        .filter(|child| child.name() == Some(tag))
+        .collect()
+}
+
 fn walk_nodes(node: Node) -> Vec<WrappedNode> {
     let mut result: Vec<WrappedNode> = Vec::new();
 
@@ -44,9 +430,11 @@ fn walk_nodes(node: Node) -> Vec<WrappedNode> {
             Data::Element(ref name, _) => {
                 let unwrapped: &str = &name.local;
 
-                if ["p", "a", "b", "i", "em", "strong", "u", "ul", "ol", "code"]
-                    .iter()
-                    .any(|tag| *tag == unwrapped)
+                if [
+                    "p", "a", "b", "i", "em", "strong", "u", "ul", "ol", "code", "table",
+                ]
+                .iter()
+                .any(|tag| *tag == unwrapped)
                 // contains requires a static string
                 {
                     result.push(WrappedNode::Element(child));
@@ -156,14 +544,30 @@ fn parse_node(node: Node, url: &str) -> (String, Vec<EmbedField>) {
                     }
                     "a" => {
                         let inner = _recur(element, &mut fields);
-                        let mut href = match element.attr("href") {
-                            Some(href) => href.to_string(),
-                            None => continue, // what's a tag without an href?
-                        };
+                        let usable_href = element
+                            .attr("href")
+                            .filter(|href| !href.is_empty() && *href != "#");
 
-                        if !href.contains("://") {
-                            href = url.to_string() + &href;
-                        }
+                        // A link whose href is missing, or whose text is a
+                        // bare dotted name (e.g. `some.module.Thing`), looks
+                        // like an unresolved domain reference (`:py:class:`
+                        // and friends) rather than an ordinary link — try
+                        // the objects.inv inventory before falling back to
+                        // whatever href we do have.
+                        let looks_unresolved =
+                            usable_href.is_none() || looks_like_domain_reference(inner.trim());
+
+                        let href = if looks_unresolved {
+                            match resolve_reference(url, inner.trim()) {
+                                Some(resolved) => resolved,
+                                None => match usable_href {
+                                    Some(href) => join_url(url, href),
+                                    None => continue,
+                                },
+                            }
+                        } else {
+                            join_url(url, usable_href.unwrap())
+                        };
 
                         result.push_str(&format!("[{}]({})", inner, href));
                     }
@@ -217,12 +621,17 @@ fn parse_node(node: Node, url: &str) -> (String, Vec<EmbedField>) {
                         } else if pending_rubric.is_some()
                             && class_list.contains(&"highlight-python3")
                         {
+                            let sections = highlight_python(&element.text());
                             fields.push(EmbedField::new(
                                 pending_rubric.take().unwrap(),
-                                format!("```py\n{}```", element.text()),
+                                format!("```ansi\n{}```", render_ansi_sections(&sections)),
                             ));
                         } else if class_list.contains(&"highlight-default") {
-                            result.push_str(&format!("```\n{}```", element.text()));
+                            let sections = highlight_python(&element.text());
+                            result.push_str(&format!(
+                                "```ansi\n{}```",
+                                render_ansi_sections(&sections)
+                            ));
                         }
 
                         let mut chunks: Vec<String> = Vec::new();
@@ -261,6 +670,135 @@ fn parse_node(node: Node, url: &str) -> (String, Vec<EmbedField>) {
                             }
                         }
                     }
+                    "table" => {
+                        // Discord embeds have no table primitive: a wide
+                        // table becomes one EmbedField per row (first column
+                        // as the name, the rest as "**header:** value" lines),
+                        // a narrow one becomes a monospace aligned block.
+                        const MAX_TABLE_CELLS: usize = 100;
+                        const MAX_TABLE_FIELDS: usize = 25; // Discord's per-embed field cap
+
+                        let body_rows = direct_children(element, "tbody")
+                            .into_iter()
+                            .flat_map(|tbody| direct_children(tbody, "tr"))
+                            .collect::<Vec<_>>();
+                        let mut rows = if body_rows.is_empty() {
+                            direct_children(element, "tr")
+                        } else {
+                            body_rows
+                        };
+
+                        let header_row = direct_children(element, "thead")
+                            .into_iter()
+                            .flat_map(|thead| direct_children(thead, "tr"))
+                            .next();
+
+                        let mut headers: Vec<String> = Vec::new();
+                        if let Some(header_row) = header_row {
+                            headers = direct_children(header_row, "th")
+                                .into_iter()
+                                .map(|th| _recur(th, &mut fields).trim().to_string())
+                                .collect();
+                        } else if let Some(first_row) = rows.first().copied() {
+                            let header_cells = direct_children(first_row, "th");
+
+                            if !header_cells.is_empty() {
+                                headers = header_cells
+                                    .into_iter()
+                                    .map(|th| _recur(th, &mut fields).trim().to_string())
+                                    .collect();
+                                rows.remove(0);
+                            }
+                        }
+
+                        let column_count = if !headers.is_empty() {
+                            headers.len()
+                        } else {
+                            rows.first()
+                                .map(|row| direct_children(*row, "td").len())
+                                .unwrap_or(0)
+                        };
+                        let narrow = column_count <= 2;
+
+                        let mut cell_budget = MAX_TABLE_CELLS;
+                        let mut aligned_rows: Vec<Vec<String>> = Vec::new();
+
+                        // Narrow tables render as a monospace block with no
+                        // other home for the column labels, so seed it with
+                        // the header row rather than silently dropping it.
+                        if narrow && !headers.is_empty() {
+                            aligned_rows.push(headers.clone());
+                        }
+
+                        for row in rows {
+                            let cells: Vec<String> = direct_children(row, "td")
+                                .into_iter()
+                                .map(|td| _recur(td, &mut fields).trim().to_string())
+                                .collect();
+
+                            if cells.is_empty() {
+                                continue;
+                            }
+
+                            if cell_budget < cells.len() {
+                                break; // past our cell-count guard; stop rather than blow past Discord's limits
+                            }
+                            cell_budget -= cells.len();
+
+                            if narrow {
+                                aligned_rows.push(cells);
+                                continue;
+                            }
+
+                            if fields.len() >= MAX_TABLE_FIELDS {
+                                break;
+                            }
+
+                            let (name, rest) = cells.split_first().unwrap();
+                            let name = if name.is_empty() {
+                                "\u{200b}".to_string() // embed field names can't be empty either
+                            } else {
+                                name.clone()
+                            };
+                            let value = if rest.is_empty() {
+                                "\u{200b}".to_string() // embed field values can't be empty
+                            } else {
+                                rest.iter()
+                                    .enumerate()
+                                    .map(|(i, cell)| match headers.get(i + 1) {
+                                        Some(header) if !header.is_empty() => {
+                                            format!("**{}:** {}", header, cell)
+                                        }
+                                        _ => cell.clone(),
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join("\n")
+                            };
+
+                            fields.push(EmbedField::new(name, value));
+                        }
+
+                        if narrow && !aligned_rows.is_empty() {
+                            let col_count = aligned_rows.iter().map(|row| row.len()).max().unwrap_or(0);
+                            let mut widths = vec![0usize; col_count];
+
+                            for row in &aligned_rows {
+                                for (i, cell) in row.iter().enumerate() {
+                                    widths[i] = widths[i].max(cell.chars().count());
+                                }
+                            }
+
+                            let mut block = String::new();
+                            for row in &aligned_rows {
+                                for (i, cell) in row.iter().enumerate() {
+                                    block.push_str(&format!("{:width$}  ", cell, width = widths[i]));
+                                }
+                                block.push('\n');
+                            }
+
+                            result.push_str(&format!("```\n{}```", block));
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -352,6 +890,252 @@ fn parse_signature_node(node: Node) -> Vec<AnsiStringSection> {
     sections
 }
 
+const PYTHON_KEYWORDS: &[&str] = &[
+    "def", "class", "return", "if", "elif", "else", "for", "while", "import", "from", "as",
+    "with", "try", "except", "finally", "lambda", "None", "True", "False", "and", "or", "not",
+    "in", "is", "pass", "break", "continue", "raise", "yield", "global", "nonlocal", "del",
+    "assert", "async", "await",
+];
+
+const PYTHON_BUILTINS: &[&str] = &[
+    "print", "len", "range", "int", "str", "float", "bool", "bytes", "list", "dict", "set",
+    "tuple", "object", "super", "self", "cls", "isinstance", "issubclass", "type", "enumerate",
+    "zip", "map", "filter", "open", "input", "sum", "min", "max", "sorted", "reversed", "abs",
+    "all", "any", "format", "repr", "id", "iter", "next", "hasattr", "getattr", "setattr",
+    "staticmethod", "classmethod", "property",
+];
+
+fn ansi_section(content: String, bold: bool, color: &str) -> AnsiStringSection {
+    AnsiStringSection {
+        content,
+        bold,
+        color: color.to_string(),
+    }
+}
+
+/// Length of the string-literal prefix (`f`, `r`, `b`, `u`, and their
+/// combinations, case-insensitive) starting at `i`, if `i` is in fact the
+/// start of a string literal.
+fn string_prefix_len(chars: &[char], i: usize) -> Option<usize> {
+    let mut len = 0;
+
+    while len < 2 && i + len < chars.len() && "fFrRbBuU".contains(chars[i + len]) {
+        len += 1;
+    }
+
+    match chars.get(i + len) {
+        Some('"') | Some('\'') => Some(len),
+        _ => None,
+    }
+}
+
+/// Consumes a string literal (with an already-known prefix length) starting
+/// at `start` and returns the index just past it. Unterminated triple-quoted
+/// strings consume to EOF; a `#` inside the string is just text.
+fn consume_string(chars: &[char], start: usize, prefix_len: usize) -> usize {
+    let quote = chars[start + prefix_len];
+    let mut i = start + prefix_len;
+
+    let triple = i + 2 < chars.len() && chars[i + 1] == quote && chars[i + 2] == quote;
+
+    if triple {
+        i += 3;
+
+        while i < chars.len() {
+            if chars[i] == '\\' {
+                i += 2;
+                continue;
+            }
+
+            if i + 2 < chars.len() && chars[i] == quote && chars[i + 1] == quote && chars[i + 2] == quote {
+                return i + 3;
+            }
+
+            i += 1;
+        }
+
+        return chars.len();
+    }
+
+    i += 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return i + 1,
+            '\n' => return i,
+            _ => i += 1,
+        }
+    }
+
+    chars.len()
+}
+
+fn consume_number(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+
+    if chars[i] == '0'
+        && matches!(
+            chars.get(i + 1),
+            Some('x') | Some('X') | Some('b') | Some('B') | Some('o') | Some('O')
+        )
+    {
+        i += 2;
+
+        while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+            i += 1;
+        }
+
+        return i;
+    }
+
+    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+        i += 1;
+    }
+
+    if chars.get(i) == Some(&'.') && matches!(chars.get(i + 1), Some(c) if c.is_ascii_digit()) {
+        i += 1;
+
+        while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+            i += 1;
+        }
+    }
+
+    if matches!(chars.get(i), Some('e') | Some('E')) {
+        let mut j = i + 1;
+
+        if matches!(chars.get(j), Some('+') | Some('-')) {
+            j += 1;
+        }
+
+        if matches!(chars.get(j), Some(c) if c.is_ascii_digit()) {
+            i = j;
+
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+    }
+
+    if matches!(chars.get(i), Some('j') | Some('J')) {
+        i += 1;
+    }
+
+    i
+}
+
+/// Hand-written token classifier for Python source, porting rustdoc's
+/// `highlight.rs` idea so code admonitions can render as real Discord ANSI
+/// code blocks instead of a plain fence. Classifies, in priority order:
+/// comments, string literals (incl. triple-quoted and `f`/`r`/`b`/`u`
+/// prefixes), numeric literals, keywords, builtins, then everything else as
+/// plain identifiers/operators.
+#[pyfunction]
+fn highlight_python(src: &str) -> Vec<AnsiStringSection> {
+    let chars: Vec<char> = src.chars().collect();
+    let n = chars.len();
+    let mut sections: Vec<AnsiStringSection> = Vec::new();
+    let mut i = 0;
+
+    while i < n {
+        let c = chars[i];
+
+        if c == '#' {
+            let start = i;
+
+            while i < n && chars[i] != '\n' {
+                i += 1;
+            }
+
+            sections.push(ansi_section(chars[start..i].iter().collect(), false, "gray"));
+            continue;
+        }
+
+        if let Some(prefix_len) = string_prefix_len(&chars, i) {
+            let end = consume_string(&chars, i, prefix_len);
+            sections.push(ansi_section(chars[i..end].iter().collect(), false, "green"));
+            i = end;
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let end = consume_number(&chars, i);
+            sections.push(ansi_section(chars[i..end].iter().collect(), false, "cyan"));
+            i = end;
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+
+            while i < n && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            let word: String = chars[start..i].iter().collect();
+
+            if PYTHON_KEYWORDS.contains(&word.as_str()) {
+                sections.push(ansi_section(word, true, "red"));
+            } else if PYTHON_BUILTINS.contains(&word.as_str()) {
+                sections.push(ansi_section(word, false, "yellow"));
+            } else {
+                sections.push(ansi_section(word, false, "white"));
+            }
+
+            continue;
+        }
+
+        let start = i;
+
+        while i < n
+            && !(chars[i] == '#'
+                || chars[i].is_ascii_digit()
+                || chars[i].is_alphabetic()
+                || chars[i] == '_'
+                || string_prefix_len(&chars, i).is_some())
+        {
+            i += 1;
+        }
+
+        if i == start {
+            i += 1;
+        }
+
+        sections.push(ansi_section(chars[start..i].iter().collect(), false, "white"));
+    }
+
+    sections
+}
+
+fn ansi_color_code(color: &str) -> &'static str {
+    match color {
+        "gray" => "30",
+        "red" => "31",
+        "green" => "32",
+        "yellow" => "33",
+        "cyan" => "36",
+        "white" => "37",
+        _ => "37",
+    }
+}
+
+/// Renders [`AnsiStringSection`]s into the ANSI escape sequences Discord's
+/// ` ```ansi ` code blocks understand.
+fn render_ansi_sections(sections: &[AnsiStringSection]) -> String {
+    let mut out = String::new();
+
+    for section in sections {
+        out.push_str(&format!(
+            "\u{1b}[{};{}m{}\u{1b}[0m",
+            if section.bold { 1 } else { 0 },
+            ansi_color_code(&section.color),
+            section.content,
+        ));
+    }
+
+    out
+}
+
 #[pyclass]
 struct SphinxDocumentResult {
     #[pyo3(get)]
@@ -364,24 +1148,7 @@ struct SphinxDocumentResult {
 
 #[pyfunction]
 fn scrape_document(url: &str, html: &str, target: &str) -> PyResult<SphinxDocumentResult> {
-    let document = unsafe {
-        let store = if let Some(store) = DOCUMENT_STORE.get_mut() {
-            store
-        } else {
-            DOCUMENT_STORE.set(HashMap::new()).unwrap();
-
-            DOCUMENT_STORE.get_mut().unwrap()
-        };
-
-        if let Some(document) = store.get(url) {
-            document
-        } else {
-            let document = Document::from(html);
-            store.insert(url.to_string(), document);
-
-            store.get(url).unwrap()  // returns the exact same document without moving it.
-        }
-    };
+    let document = get_or_insert_document(url, html);
 
     let predicate = Name("dt").and(Attr("id", target));
     let signature = document
@@ -417,9 +1184,17 @@ fn app_native(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pyfunction!(hello_world))?;
     m.add_wrapped(wrap_pyfunction!(has_document))?;
     m.add_wrapped(wrap_pyfunction!(scrape_document))?;
+    m.add_wrapped(wrap_pyfunction!(set_document_store_capacity))?;
+    m.add_wrapped(wrap_pyfunction!(build_index))?;
+    m.add_wrapped(wrap_pyfunction!(search_index))?;
+    m.add_wrapped(wrap_pyfunction!(highlight_python))?;
+    m.add_wrapped(wrap_pyfunction!(load_inventory))?;
+    m.add_wrapped(wrap_pyfunction!(resolve_reference))?;
     m.add_class::<EmbedField>()?;
     m.add_class::<AnsiStringSection>()?;
     m.add_class::<SphinxDocumentResult>()?;
+    m.add_class::<IndexEntry>()?;
+    m.add_class::<ResolvedRef>()?;
 
     Ok(())
 }